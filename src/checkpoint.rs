@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use log::warn;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::serum::{normalize_fills, EventQueue, NormalizedFill};
+
+/// Tracks the last sequence number emitted per market so that repeated
+/// polls/pushes of the same event queue only emit events newer than what's
+/// already been sent downstream.
+///
+/// The event queue is a ring buffer: `header.seq_num` is the next slot to be
+/// written and the live region is the `count` events ending there, so the
+/// oldest live event carries sequence `header.seq_num - header.count`.
+///
+/// Checkpoints are stored as `i128`, one below the real `u64` sequence
+/// space, so "nothing seen yet" / "resync to just before the live window"
+/// can be represented as `base_seq_num - 1` even when `base_seq_num` is 0 —
+/// a plain `u64` with a saturating decrement would clamp that to `0` and
+/// then wrongly treat the real seq_num `0` fill as already seen.
+#[derive(Default)]
+pub struct Checkpoints {
+    last_seen: HashMap<Pubkey, i128>,
+}
+
+impl Checkpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Normalizes `queue`'s fills and filters out anything at or before the
+    /// market's checkpoint, advancing it to the highest sequence number
+    /// emitted. If the live window has already moved past the checkpoint
+    /// (the consumer fell behind further than the queue's capacity), logs
+    /// the gap and resyncs to the current live window instead of erroring.
+    pub fn apply(
+        &mut self,
+        market: &Pubkey,
+        queue: EventQueue,
+        coin_lot_size: u64,
+        pc_lot_size: u64,
+    ) -> anyhow::Result<Vec<NormalizedFill>> {
+        let base_seq_num = i128::from(queue.header.seq_num.saturating_sub(queue.header.count()));
+        let since = *self.last_seen.entry(*market).or_insert(base_seq_num - 1);
+
+        if base_seq_num > since + 1 {
+            warn!(
+                "market {market}: consumer fell behind (checkpoint {since}, live window now starts at {base_seq_num}); resyncing"
+            );
+        }
+        let since = since.max(base_seq_num - 1);
+
+        let fills: Vec<NormalizedFill> = normalize_fills(queue, coin_lot_size, pc_lot_size)?
+            .into_iter()
+            .filter(|fill| i128::from(fill.seq_num) > since)
+            .collect();
+
+        if let Some(max_seq) = fills.iter().map(|fill| i128::from(fill.seq_num)).max() {
+            self.last_seen.insert(*market, max_seq);
+        } else {
+            self.last_seen.insert(*market, since);
+        }
+
+        Ok(fills)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serum_dex::matching::Side;
+    use serum_dex::state::{EventQueueHeader, EventView, FeeTier};
+
+    use super::*;
+
+    fn fill_event() -> EventView {
+        EventView::Fill {
+            side: Side::Bid,
+            maker: true,
+            native_qty_paid: 100,
+            native_qty_received: 10,
+            native_fee_or_rebate: 0,
+            order_id: 0,
+            owner: [0; 4],
+            owner_slot: 0,
+            fee_tier: FeeTier::Base,
+            client_order_id: None,
+        }
+    }
+
+    /// A queue whose live events start at `head`, with `count` events live
+    /// and the header's next-write position at `seq_num`.
+    fn queue_with(head: u64, count: u64, seq_num: u64) -> EventQueue {
+        EventQueue {
+            header: EventQueueHeader {
+                account_flags: 0,
+                head,
+                count,
+                seq_num,
+            },
+            events: (0..count).map(|_| fill_event()).collect(),
+        }
+    }
+
+    #[test]
+    fn first_fill_at_seq_zero_is_not_dropped() {
+        let mut checkpoints = Checkpoints::new();
+        let market = Pubkey::new_unique();
+        // A fresh queue: one live event, right at the very first seq_num.
+        let queue = queue_with(0, 1, 1);
+
+        let fills = checkpoints.apply(&market, queue, 1, 1).unwrap();
+
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].seq_num, 0);
+    }
+
+    #[test]
+    fn repeated_poll_of_same_queue_emits_nothing_new() {
+        let mut checkpoints = Checkpoints::new();
+        let market = Pubkey::new_unique();
+
+        checkpoints.apply(&market, queue_with(0, 1, 1), 1, 1).unwrap();
+        let fills = checkpoints.apply(&market, queue_with(0, 1, 1), 1, 1).unwrap();
+
+        assert!(fills.is_empty());
+    }
+}
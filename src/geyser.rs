@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use anyhow::{format_err, Result};
+use futures_util::StreamExt;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc;
+use yellowstone_grpc_client::GeyserGrpcClient;
+use yellowstone_grpc_proto::prelude::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeUpdate,
+};
+
+use crate::serum::{decode_event_queue, get_keys_for_market};
+use crate::source::EventQueueFeed;
+
+/// Subscribes to a market's `event_q` account over a Yellowstone gRPC
+/// geyser stream, re-decoding it on every account write.
+///
+/// Each call opens its own gRPC connection whose account filter names only
+/// this market's `event_q` pubkey; it does not filter by `owner` and does
+/// not share a connection across markets.
+pub async fn stream_event_queue(
+    client: &RpcClient,
+    geyser_endpoint: &str,
+    x_token: Option<&str>,
+    program_id: &Pubkey,
+    market: &Pubkey,
+) -> Result<EventQueueFeed> {
+    let market_keys = get_keys_for_market(client, program_id, market)?;
+    let event_q = market_keys.event_q.to_string();
+
+    let mut geyser = GeyserGrpcClient::connect(geyser_endpoint.to_string(), x_token.map(str::to_string), None)
+        .await
+        .map_err(|e| format_err!("failed to connect to geyser endpoint: {:?}", e))?;
+
+    let mut accounts = HashMap::new();
+    accounts.insert(
+        "event_q".to_string(),
+        SubscribeRequestFilterAccounts {
+            account: vec![event_q],
+            owner: vec![],
+            filters: vec![],
+        },
+    );
+
+    let mut update_stream = geyser
+        .subscribe_once(SubscribeRequest {
+            accounts,
+            slots: HashMap::new(),
+            transactions: HashMap::new(),
+            transactions_status: HashMap::new(),
+            blocks: HashMap::new(),
+            blocks_meta: HashMap::new(),
+            entry: HashMap::new(),
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+            accounts_data_slice: vec![],
+            ping: None,
+        })
+        .await
+        .map_err(|e| format_err!("geyser subscribe failed: {:?}", e))?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(update) = update_stream.next().await {
+            if let Some(decoded) = decode_update(update) {
+                if tx.send(decoded).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(EventQueueFeed {
+        coin_lot_size: market_keys.coin_lot_size,
+        pc_lot_size: market_keys.pc_lot_size,
+        updates: rx,
+    })
+}
+
+fn decode_update(
+    update: Result<SubscribeUpdate, tonic::Status>,
+) -> Option<Result<crate::serum::EventQueue>> {
+    let update = match update {
+        Ok(update) => update,
+        Err(status) => return Some(Err(format_err!("geyser stream error: {}", status))),
+    };
+    match update.update_oneof? {
+        UpdateOneof::Account(account_update) => {
+            let account = account_update.account?;
+            Some(decode_event_queue(&account.data))
+        }
+        _ => None,
+    }
+}
@@ -1,123 +1,417 @@
-use std::borrow::Cow;
-use std::cell::RefMut;
-use std::convert::identity;
+mod checkpoint;
+mod geyser;
+mod orderbook;
+mod serum;
+mod snapshot;
+mod source;
+mod stream;
+
 use std::str::FromStr;
+use std::time::Duration;
 
-use anyhow::{format_err, Result};
-use arrayref::mut_array_refs;
-use safe_transmute::{transmute_many_pedantic, transmute_one_pedantic, transmute_one_to_bytes, transmute_to_bytes};
-use serum_dex::error::DexResult;
-use serum_dex::state::{
-    gen_vault_signer_key, AccountFlag, Market, MarketState, MarketStateV2, ACCOUNT_HEAD_PADDING,
-    ACCOUNT_TAIL_PADDING,
-};
+use anyhow::{format_err, Context, Result};
+use clap::{App, Arg};
+use log::{info, warn};
+use rdkafka::config::ClientConfig;
+use rdkafka::message::OwnedHeaders;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use serde::Serialize;
+use serum_dex::matching::Side;
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::account::Account;
-use solana_sdk::account_info::AccountInfo;
 use solana_sdk::pubkey::Pubkey;
 
-#[derive(Debug)]
-pub struct MarketPubkeys {
-    pub market: Box<Pubkey>,
-    pub req_q: Box<Pubkey>,
-    pub event_q: Box<Pubkey>,
-    pub bids: Box<Pubkey>,
-    pub asks: Box<Pubkey>,
-    pub coin_vault: Box<Pubkey>,
-    pub pc_vault: Box<Pubkey>,
-    pub vault_signer_key: Box<Pubkey>,
+use crate::checkpoint::Checkpoints;
+use crate::orderbook::{BookDiff, LevelChange};
+use crate::serum::NormalizedFill;
+use crate::snapshot::{load_markets_snapshot, MarketSnapshot};
+use crate::source::{EventQueueSource, Geyser, RpcPoll, WebSocket};
+
+/// Wire representation of a [`NormalizedFill`], published to Kafka as JSON.
+#[derive(Serialize)]
+struct FillMessage {
+    market: String,
+    seq_num: u64,
+    side: &'static str,
+    price_quote_lots: u64,
+    price_base_lots: u64,
+    native_base_size: u64,
+    native_quote_size: u64,
+    maker: bool,
+    client_order_id: Option<u64>,
 }
 
-#[cfg(target_endian = "little")]
-fn remove_dex_account_padding<'a>(data: &'a [u8]) -> Result<Cow<'a, [u64]>> {
-    use serum_dex::state::{ACCOUNT_HEAD_PADDING, ACCOUNT_TAIL_PADDING};
-    let head = &data[..ACCOUNT_HEAD_PADDING.len()];
-    if data.len() < ACCOUNT_HEAD_PADDING.len() + ACCOUNT_TAIL_PADDING.len() {
-        return Err(format_err!(
-            "dex account length {} is too small to contain valid padding",
-            data.len()
-        ));
+impl FillMessage {
+    fn from_fill(market: &Pubkey, fill: &NormalizedFill) -> Self {
+        FillMessage {
+            market: market.to_string(),
+            seq_num: fill.seq_num,
+            side: match fill.side {
+                Side::Bid => "bid",
+                Side::Ask => "ask",
+            },
+            price_quote_lots: fill.price_quote_lots,
+            price_base_lots: fill.price_base_lots,
+            native_base_size: fill.native_base_size,
+            native_quote_size: fill.native_quote_size,
+            maker: fill.maker,
+            client_order_id: fill.client_order_id,
+        }
     }
-    if head != ACCOUNT_HEAD_PADDING {
-        return Err(format_err!("dex account head padding mismatch"));
+}
+
+/// Wire representation of one [`LevelChange`], published as part of a
+/// [`BookDiffMessage`].
+#[derive(Serialize)]
+struct LevelChangeMessage {
+    kind: &'static str,
+    price_lots: u64,
+    native_quantity_lots: Option<u64>,
+}
+
+impl From<LevelChange> for LevelChangeMessage {
+    fn from(change: LevelChange) -> Self {
+        match change {
+            LevelChange::Added(level) => LevelChangeMessage {
+                kind: "added",
+                price_lots: level.price_lots,
+                native_quantity_lots: Some(level.native_quantity_lots),
+            },
+            LevelChange::Updated(level) => LevelChangeMessage {
+                kind: "updated",
+                price_lots: level.price_lots,
+                native_quantity_lots: Some(level.native_quantity_lots),
+            },
+            LevelChange::Removed { price_lots } => LevelChangeMessage {
+                kind: "removed",
+                price_lots,
+                native_quantity_lots: None,
+            },
+        }
+    }
+}
+
+/// Wire representation of a [`BookDiff`], published to Kafka as JSON.
+#[derive(Serialize)]
+struct BookDiffMessage {
+    market: String,
+    bids: Vec<LevelChangeMessage>,
+    asks: Vec<LevelChangeMessage>,
+}
+
+impl BookDiffMessage {
+    fn from_diff(market: &Pubkey, diff: BookDiff) -> Self {
+        BookDiffMessage {
+            market: market.to_string(),
+            bids: diff.bids.into_iter().map(Into::into).collect(),
+            asks: diff.asks.into_iter().map(Into::into).collect(),
+        }
     }
-    let tail = &data[data.len() - ACCOUNT_TAIL_PADDING.len()..];
-    if tail != ACCOUNT_TAIL_PADDING {
-        return Err(format_err!("dex account tail padding mismatch"));
+}
+
+/// Publishes each fill to `topic`, keyed by the market address so that all
+/// events for one market land in the same partition for ordering.
+async fn publish_fills(
+    producer: &FutureProducer,
+    topic: &str,
+    market: &Pubkey,
+    fills: &[NormalizedFill],
+) -> Result<()> {
+    let market_key = market.to_string();
+    for fill in fills {
+        let message = FillMessage::from_fill(market, fill);
+        let payload = serde_json::to_vec(&message)?;
+        let headers = OwnedHeaders::new()
+            .add("market", market_key.as_str())
+            .add("seq_num", &fill.seq_num.to_string());
+
+        producer
+            .send(
+                FutureRecord::to(topic)
+                    .payload(&payload)
+                    .key(&market_key)
+                    .headers(headers),
+                Duration::from_secs(0),
+            )
+            .await
+            .map_err(|(e, _)| e)
+            .with_context(|| format!("failed to publish fill seq_num={}", fill.seq_num))?;
+
+        info!(
+            "published fill seq_num={} market={}",
+            fill.seq_num, market_key
+        );
     }
-    let inner_data_range = ACCOUNT_HEAD_PADDING.len()..(data.len() - ACCOUNT_TAIL_PADDING.len());
-    let inner: &'a [u8] = &data[inner_data_range];
-    let words: Cow<'a, [u64]> = match transmute_many_pedantic::<u64>(inner) {
-        Ok(word_slice) => Cow::Borrowed(word_slice),
-        Err(transmute_error) => {
-            let word_vec = transmute_error.copy().map_err(|e| e.without_src())?;
-            Cow::Owned(word_vec)
+    Ok(())
+}
+
+/// Publishes one market's order-book diff to `topic`, keyed by the market
+/// address like [`publish_fills`].
+async fn publish_book_diff(
+    producer: &FutureProducer,
+    topic: &str,
+    market: &Pubkey,
+    diff: BookDiff,
+) -> Result<()> {
+    let market_key = market.to_string();
+    let message = BookDiffMessage::from_diff(market, diff);
+    let payload = serde_json::to_vec(&message)?;
+
+    producer
+        .send(
+            FutureRecord::to(topic).payload(&payload).key(&market_key),
+            Duration::from_secs(0),
+        )
+        .await
+        .map_err(|(e, _)| e)
+        .with_context(|| format!("failed to publish book diff for market {market_key}"))?;
+
+    info!("published book diff market={}", market_key);
+    Ok(())
+}
+
+/// Spawns the background task that watches `market`'s order book (via
+/// [`orderbook::watch_l2_diffs`]) and publishes each diff to `topic`.
+fn spawn_book_diff_publisher(
+    producer: FutureProducer,
+    topic: String,
+    rpc_url: String,
+    program_id: Pubkey,
+    market: Pubkey,
+    interval: Duration,
+) -> Result<()> {
+    let mut diffs = orderbook::watch_l2_diffs(rpc_url, program_id, market, interval)?;
+    tokio::spawn(async move {
+        while let Some(diff) = diffs.recv().await {
+            match diff {
+                Ok(diff) if diff.bids.is_empty() && diff.asks.is_empty() => {}
+                Ok(diff) => {
+                    if let Err(e) = publish_book_diff(&producer, &topic, &market, diff).await {
+                        warn!("failed to publish book diff for market {market}: {e:#}");
+                    }
+                }
+                Err(e) => warn!("order-book poll failed for market {market}: {e:#}"),
+            }
         }
-    };
-    Ok(words)
+    });
+    Ok(())
 }
 
-#[cfg(target_endian = "little")]
-fn get_keys_for_market<'a>(
-    client: &'a RpcClient,
-    program_id: &'a Pubkey,
-    market: &'a Pubkey,
-) -> Result<MarketPubkeys> {
-    let account_data: Vec<u8> = client.get_account_data(&market)?;
-    let words: Cow<[u64]> = remove_dex_account_padding(&account_data)?;
-    let market_state: MarketState = {
-        let account_flags = Market::account_flags(&account_data)?;
-        if account_flags.intersects(AccountFlag::Permissioned) {
-            let state = transmute_one_pedantic::<MarketStateV2>(transmute_to_bytes(&words))
-                .map_err(|e| e.without_src())?;
-            state.check_flags(true)?;
-            state.inner
-        } else {
-            let state = transmute_one_pedantic::<MarketState>(transmute_to_bytes(&words))
-                .map_err(|e| e.without_src())?;
-            state.check_flags(true)?;
-            state
+/// Watches many markets at once by re-loading all of their event queues and
+/// order books together on each tick via [`snapshot::load_markets_snapshot`],
+/// instead of running one [`EventQueueSource`] per market.
+async fn run_multi_market(
+    producer: FutureProducer,
+    topic: String,
+    rpc_url: String,
+    program_id: Pubkey,
+    markets: Vec<Pubkey>,
+    poll_interval: Duration,
+) -> Result<()> {
+    let client = RpcClient::new(rpc_url);
+    let mut checkpoints = Checkpoints::new();
+
+    loop {
+        let snapshots = load_markets_snapshot(&client, &program_id, &markets)?;
+        for (market, snapshot) in snapshots {
+            let MarketSnapshot { keys, event_queue, order_book } = snapshot;
+            info!(
+                "market {market}: queue_len={} bids_levels={} asks_levels={}",
+                event_queue.events.len(),
+                order_book.bids.levels.len(),
+                order_book.asks.levels.len(),
+            );
+
+            let fills =
+                checkpoints.apply(&market, event_queue, keys.coin_lot_size, keys.pc_lot_size)?;
+            if !fills.is_empty() {
+                publish_fills(&producer, &topic, &market, &fills).await?;
+            }
         }
-    };
-    let vault_signer_key =
-        gen_vault_signer_key(market_state.vault_signer_nonce, market, program_id)?;
-    assert_eq!(
-        transmute_to_bytes(&identity(market_state.own_address)),
-        market.as_ref()
-    );
-    Ok(MarketPubkeys {
-        market: Box::new(*market),
-        req_q: Box::new(Pubkey::new(transmute_one_to_bytes(&identity(
-            market_state.req_q,
-        )))),
-        event_q: Box::new(Pubkey::new(transmute_one_to_bytes(&identity(
-            market_state.event_q,
-        )))),
-        bids: Box::new(Pubkey::new(transmute_one_to_bytes(&identity(
-            market_state.bids,
-        )))),
-        asks: Box::new(Pubkey::new(transmute_one_to_bytes(&identity(
-            market_state.asks,
-        )))),
-        coin_vault: Box::new(Pubkey::new(transmute_one_to_bytes(&identity(
-            market_state.coin_vault,
-        )))),
-        pc_vault: Box::new(Pubkey::new(transmute_one_to_bytes(&identity(
-            market_state.pc_vault,
-        )))),
-        vault_signer_key: Box::new(vault_signer_key),
-    })
+        tokio::time::sleep(poll_interval).await;
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let client = RpcClient::new("https://solana-api.projectserum.com".to_string());
-    let program_id = Pubkey::from_str("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin")?;
-    // sol usdc
-    let market = Pubkey::from_str("9wFFyRfZBsuAha4YcuxcXLKwMxJR43S7fPfQLusDBzvT")?;
+    env_logger::init();
+
+    let matches = App::new("serum-iq")
+        .version(option_env!("CARGO_PKG_VERSION").unwrap_or(""))
+        .about("Streams decoded serum dex fills onto a Kafka topic")
+        .arg(
+            Arg::new("brokers")
+                .short('b')
+                .long("brokers")
+                .help("Broker list in kafka format")
+                .takes_value(true)
+                .default_value("localhost:9092"),
+        )
+        .arg(
+            Arg::new("topic")
+                .short('t')
+                .long("topic")
+                .help("Destination topic")
+                .takes_value(true)
+                .default_value("serum-fills"),
+        )
+        .arg(
+            Arg::new("program-id")
+                .long("program-id")
+                .help("Serum dex program id to resolve the market under")
+                .takes_value(true)
+                .default_value("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin"),
+        )
+        .arg(
+            Arg::new("market")
+                .long("market")
+                .help("Market address to watch")
+                .takes_value(true)
+                .default_value("9wFFyRfZBsuAha4YcuxcXLKwMxJR43S7fPfQLusDBzvT"),
+        )
+        .arg(
+            Arg::new("markets")
+                .long("markets")
+                .help(
+                    "Comma-separated market addresses to batch-watch via getMultipleAccounts, \
+                     overriding --source/--market",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("multi-market-poll-interval-ms")
+                .long("multi-market-poll-interval-ms")
+                .help("Polling interval for --markets")
+                .takes_value(true)
+                .default_value("1000"),
+        )
+        .arg(
+            Arg::new("rpc-url")
+                .long("rpc-url")
+                .help("Solana JSON-RPC endpoint")
+                .takes_value(true)
+                .default_value("https://solana-api.projectserum.com"),
+        )
+        .arg(
+            Arg::new("source")
+                .long("source")
+                .help("Ingestion backend to watch the market's event queue with")
+                .takes_value(true)
+                .possible_values(["rpc-poll", "websocket", "geyser"])
+                .default_value("rpc-poll"),
+        )
+        .arg(
+            Arg::new("poll-interval-ms")
+                .long("poll-interval-ms")
+                .help("Polling interval for --source=rpc-poll")
+                .takes_value(true)
+                .default_value("1000"),
+        )
+        .arg(
+            Arg::new("ws-url")
+                .long("ws-url")
+                .help("Websocket endpoint for --source=websocket")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("geyser-endpoint")
+                .long("geyser-endpoint")
+                .help("Yellowstone gRPC endpoint for --source=geyser")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("geyser-x-token")
+                .long("geyser-x-token")
+                .help("Auth token for --source=geyser")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("watch-book")
+                .long("watch-book")
+                .help("Also publish incremental order-book diffs to --book-topic")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("book-topic")
+                .long("book-topic")
+                .help("Destination topic for order-book diffs, used with --watch-book")
+                .takes_value(true)
+                .default_value("serum-book-diffs"),
+        )
+        .arg(
+            Arg::new("book-poll-interval-ms")
+                .long("book-poll-interval-ms")
+                .help("Polling interval for --watch-book")
+                .takes_value(true)
+                .default_value("1000"),
+        )
+        .get_matches();
 
-    let market_keys = get_keys_for_market(&client, &program_id, &market)?;
-    println!("{market_keys:?}");
+    let brokers = matches.value_of("brokers").unwrap();
+    let topic = matches.value_of("topic").unwrap();
+    let program_id = Pubkey::from_str(matches.value_of("program-id").unwrap())?;
+    let market = Pubkey::from_str(matches.value_of("market").unwrap())?;
+    let rpc_url = matches.value_of("rpc-url").unwrap().to_string();
+
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", brokers)
+        .set("message.timeout.ms", "5000")
+        .create()
+        .context("producer creation error")?;
+
+    if let Some(markets_arg) = matches.value_of("markets") {
+        let markets: Vec<Pubkey> = markets_arg
+            .split(',')
+            .map(|s| Pubkey::from_str(s.trim()).map_err(anyhow::Error::from))
+            .collect::<Result<_>>()?;
+        let poll_interval = Duration::from_millis(matches.value_of_t("multi-market-poll-interval-ms")?);
+        info!("watching {} markets via chunked getMultipleAccounts", markets.len());
+        return run_multi_market(producer, topic.to_string(), rpc_url, program_id, markets, poll_interval)
+            .await;
+    }
+
+    if matches.is_present("watch-book") {
+        spawn_book_diff_publisher(
+            producer.clone(),
+            matches.value_of("book-topic").unwrap().to_string(),
+            rpc_url.clone(),
+            program_id,
+            market,
+            Duration::from_millis(matches.value_of_t("book-poll-interval-ms")?),
+        )?;
+    }
+
+    let source: Box<dyn EventQueueSource> = match matches.value_of("source").unwrap() {
+        "rpc-poll" => Box::new(RpcPoll {
+            rpc_url,
+            interval: Duration::from_millis(matches.value_of_t("poll-interval-ms")?),
+        }),
+        "websocket" => Box::new(WebSocket {
+            ws_url: matches
+                .value_of("ws-url")
+                .ok_or_else(|| format_err!("--ws-url is required for --source=websocket"))?
+                .to_string(),
+            rpc_url: rpc_url.clone(),
+        }),
+        "geyser" => Box::new(Geyser {
+            rpc_url,
+            endpoint: matches
+                .value_of("geyser-endpoint")
+                .ok_or_else(|| format_err!("--geyser-endpoint is required for --source=geyser"))?
+                .to_string(),
+            x_token: matches.value_of("geyser-x-token").map(str::to_string),
+        }),
+        other => return Err(format_err!("unknown source {other}")),
+    };
+
+    info!("watching market {} via {:?}", market, matches.value_of("source"));
+    let mut feed = source.subscribe(&program_id, &market).await?;
+    let mut checkpoints = Checkpoints::new();
+    while let Some(queue) = feed.updates.recv().await {
+        let fills = checkpoints.apply(&market, queue?, feed.coin_lot_size, feed.pc_lot_size)?;
+        if !fills.is_empty() {
+            publish_fills(&producer, topic, &market, &fills).await?;
+        }
+    }
 
     Ok(())
 }
@@ -26,6 +26,8 @@ pub struct MarketPubkeys {
     pub coin_vault: Box<Pubkey>,
     pub pc_vault: Box<Pubkey>,
     pub vault_signer_key: Box<Pubkey>,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
 }
 
 pub struct EventQueue {
@@ -33,76 +35,176 @@ pub struct EventQueue {
     pub events: Vec<EventView>,
 }
 
-pub fn load_event_queue(client: &RpcClient, dex_program_id: &Pubkey, market: &Pubkey) -> anyhow::Result<()> {
+/// A normalized trade record derived from a decoded `Fill` event.
+///
+/// `price_quote_lots` / `price_base_lots` represent the exact fill price as
+/// a quote-lots-per-base-lot ratio; keeping it as a rational pair instead of
+/// pre-dividing avoids float drift and lets callers pick their own rounding.
+/// `seq_num` is the event's effective position in the queue's monotonic
+/// sequence, per `EventQueueHeader::seq_num`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizedFill {
+    pub seq_num: u64,
+    pub side: Side,
+    pub price_quote_lots: u64,
+    pub price_base_lots: u64,
+    pub native_base_size: u64,
+    pub native_quote_size: u64,
+    pub maker: bool,
+    pub client_order_id: Option<u64>,
+}
+
+/// Fetches a market's event queue, decodes it, and normalizes every `Fill`
+/// into a trade record ready to publish.
+pub fn load_event_queue(
+    client: &RpcClient,
+    dex_program_id: &Pubkey,
+    market: &Pubkey,
+) -> anyhow::Result<Vec<NormalizedFill>> {
     let market_keys = get_keys_for_market(&client, dex_program_id, &market)?;
     let event_q_data = client.get_account_data(&market_keys.event_q)?;
-    let inner: Cow<[u64]> = remove_dex_account_padding(&event_q_data)?;
-    let event_queue = parse_event_queue(&inner)?;
-    Ok(())
+    let event_queue = decode_event_queue(&event_q_data)?;
+    normalize_fills(
+        event_queue,
+        market_keys.coin_lot_size,
+        market_keys.pc_lot_size,
+    )
 }
 
-fn parse_event_queue(data_words: &[u64]) -> anyhow::Result<EventQueue> {
+/// Decodes a raw `event_q` account's data into an [`EventQueue`], stripping
+/// the dex account padding first. Takes plain bytes rather than an `RpcClient`
+/// so the same decoding logic serves single-account and batched
+/// (`getMultipleAccounts`) callers alike.
+pub(crate) fn decode_event_queue(account_data: &[u8]) -> anyhow::Result<EventQueue> {
+    let inner: Cow<[u64]> = remove_dex_account_padding(account_data)?;
+    parse_event_queue(&inner)
+}
+
+pub(crate) fn parse_event_queue(data_words: &[u64]) -> anyhow::Result<EventQueue> {
     let (header_words, event_words) = data_words.split_at(size_of::<EventQueueHeader>() >> 3);
     let header: EventQueueHeader =
         transmute_one_pedantic(transmute_to_bytes(header_words)).map_err(|e| e.without_src())?;
     let events: &[Event] = transmute_many::<_, SingleManyGuard>(transmute_to_bytes(event_words))
         .map_err(|e| e.without_src())?;
-    let (_, head_seg) = events.split_at(header.head() as usize);
-    let head_len = head_seg.len().min(header.count() as usize);
 
-    Ok((EventQueue {
-        header,
-        events: Vec::from(&head_seg[..head_len].map(|e| e.as_view()?))
-    }))
+    // The live window is the `count` events starting at `head`, wrapping
+    // through index 0 once `head + count` passes the capacity: take
+    // whatever fits after `head` first, then whatever spilled over to the
+    // front of the ring.
+    let head = header.head() as usize;
+    let count = header.count() as usize;
+    let (_, head_seg) = events.split_at(head);
+    let head_len = head_seg.len().min(count);
+    let wrapped_len = count - head_len;
+
+    let events = head_seg[..head_len]
+        .iter()
+        .chain(&events[..wrapped_len])
+        .map(|e| e.as_view().map_err(|e| format_err!("failed to decode event: {:?}", e)))
+        .collect::<anyhow::Result<Vec<EventView>>>()?;
+
+    Ok(EventQueue { header, events })
+}
+
+/// Normalizes every `Fill` in a decoded event queue into a [`NormalizedFill`],
+/// tagging each with its effective sequence number (see
+/// [`NormalizedFill::seq_num`]) and skipping `Out` events (order
+/// cancellations / settle-release notices).
+pub fn normalize_fills(
+    queue: EventQueue,
+    coin_lot_size: u64,
+    pc_lot_size: u64,
+) -> anyhow::Result<Vec<NormalizedFill>> {
+    let EventQueue { header, events } = queue;
+    let base_seq_num = header.seq_num.saturating_sub(header.count());
+    events
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, event)| {
+            let seq_num = base_seq_num + i as u64;
+            parse_event(event, seq_num, coin_lot_size, pc_lot_size).transpose()
+        })
+        .collect()
 }
 
-// fn parse_event(event: Event) -> anyhow::Result<()> {
-//     match event.as_view()? {
-//         EventView::Fill {
-//             side,
-//             maker,
-//             native_qty_paid,
-//             native_qty_received,
-//             native_fee_or_rebate,
-//             fee_tier: _,
-//             order_id: _,
-//             owner: _,
-//             owner_slot,
-//             client_order_id,
-//         } => {
-//             native_qty_paid
-//                 .checked_add(native_fee_or_rebate)
-//                 .ok_or()
-//             let mut price = if maker {
-//                 native_qty_paid + native_fee_or_rebate
-//             } else {
-//                 native_qty_paid - native_fee_or_rebate
-//             };
-//             match side {
-//                 Side::Bid => {
-//                     price =
-//                 }
-//                 Side::Ask => {
-//                 }
-//             }
-//         },
-//         EventView::Out {
-//                side,
-//                release_funds,
-//                native_qty_unlocked,
-//                native_qty_still_locked,
-//                order_id: _,
-//                owner: _,
-//                owner_slot,
-//                client_order_id,
-//            } => {
-//         }
-//     };
-//     Ok(())
-// }
+/// Turns a single decoded event into a [`NormalizedFill`], or `None` for
+/// non-fill events.
+///
+/// The fee/rebate is always denominated in pc (quote), so it adjusts
+/// whichever leg is the quote one: `native_qty_paid` for a `Bid`,
+/// `native_qty_received` for an `Ask`. Whether that adjustment is a credit
+/// (add) or a debit (subtract) also depends on the side, since "paid" and
+/// "received" swap meaning between `Bid` and `Ask` — a maker rebate is a
+/// credit on a `Bid` but a debit on an `Ask`, and a taker fee is the
+/// opposite of whatever the maker gets on the same side.
+fn parse_event(
+    event: EventView,
+    seq_num: u64,
+    coin_lot_size: u64,
+    pc_lot_size: u64,
+) -> anyhow::Result<Option<NormalizedFill>> {
+    let (side, maker, native_qty_paid, native_qty_received, native_fee_or_rebate, client_order_id) =
+        match event {
+            EventView::Fill {
+                side,
+                maker,
+                native_qty_paid,
+                native_qty_received,
+                native_fee_or_rebate,
+                client_order_id,
+                ..
+            } => (
+                side,
+                maker,
+                native_qty_paid,
+                native_qty_received,
+                native_fee_or_rebate,
+                client_order_id,
+            ),
+            EventView::Out { .. } => return Ok(None),
+        };
+
+    // The quote leg is `native_qty_paid` for a Bid but `native_qty_received`
+    // for an Ask, which flips whether a maker rebate is a credit or a debit.
+    let quote_is_received = matches!(side, Side::Ask);
+    let apply_fee = |native_amount: u64| -> anyhow::Result<u64> {
+        if maker ^ quote_is_received {
+            native_amount
+                .checked_add(native_fee_or_rebate)
+                .ok_or_else(|| format_err!("fill amount overflowed applying fee/rebate"))
+        } else {
+            native_amount
+                .checked_sub(native_fee_or_rebate)
+                .ok_or_else(|| format_err!("fill amount underflowed applying fee/rebate"))
+        }
+    };
+
+    let (native_quote, native_base) = match side {
+        Side::Bid => (apply_fee(native_qty_paid)?, native_qty_received),
+        Side::Ask => (apply_fee(native_qty_received)?, native_qty_paid),
+    };
+
+    let price_quote_lots = native_quote
+        .checked_div(pc_lot_size)
+        .ok_or_else(|| format_err!("pc_lot_size must be non-zero"))?;
+    let price_base_lots = native_base
+        .checked_div(coin_lot_size)
+        .ok_or_else(|| format_err!("coin_lot_size must be non-zero"))?;
+
+    Ok(Some(NormalizedFill {
+        seq_num,
+        side,
+        price_quote_lots,
+        price_base_lots,
+        native_base_size: native_base,
+        native_quote_size: native_quote,
+        maker,
+        client_order_id,
+    }))
+}
 
 #[cfg(target_endian = "little")]
-fn remove_dex_account_padding<'a>(data: &'a [u8]) -> anyhow::Result<Cow<'a, [u64]>> {
+pub(crate) fn remove_dex_account_padding<'a>(data: &'a [u8]) -> anyhow::Result<Cow<'a, [u64]>> {
     use serum_dex::state::{ACCOUNT_HEAD_PADDING, ACCOUNT_TAIL_PADDING};
     let head = &data[..ACCOUNT_HEAD_PADDING.len()];
     if data.len() < ACCOUNT_HEAD_PADDING.len() + ACCOUNT_TAIL_PADDING.len() {
@@ -131,13 +233,26 @@ fn remove_dex_account_padding<'a>(data: &'a [u8]) -> anyhow::Result<Cow<'a, [u64
 }
 
 #[cfg(target_endian = "little")]
-fn get_keys_for_market<'a>(
+pub(crate) fn get_keys_for_market<'a>(
     client: &'a RpcClient,
     program_id: &'a Pubkey,
     market: &'a Pubkey,
 ) -> anyhow::Result<MarketPubkeys> {
     let account_data: Vec<u8> = client.get_account_data(&market)?;
-    let words: Cow<[u64]> = remove_dex_account_padding(&account_data)?;
+    decode_market_keys(&account_data, program_id, market)
+}
+
+/// Decodes a raw market account's data into its [`MarketPubkeys`]. Split out
+/// from [`get_keys_for_market`] so [`crate::snapshot`]'s batched loader can
+/// decode keys fetched via `getMultipleAccounts` without an RPC round trip
+/// of its own.
+#[cfg(target_endian = "little")]
+pub(crate) fn decode_market_keys(
+    account_data: &[u8],
+    program_id: &Pubkey,
+    market: &Pubkey,
+) -> anyhow::Result<MarketPubkeys> {
+    let words: Cow<[u64]> = remove_dex_account_padding(account_data)?;
     let market_state: MarketState = {
         let account_flags = Market::account_flags(&account_data)?;
         if account_flags.intersects(AccountFlag::Permissioned) {
@@ -179,6 +294,8 @@ fn get_keys_for_market<'a>(
             market_state.pc_vault,
         )))),
         vault_signer_key: Box::new(vault_signer_key),
+        coin_lot_size: market_state.coin_lot_size,
+        pc_lot_size: market_state.pc_lot_size,
     })
 }
 
@@ -186,6 +303,8 @@ fn get_keys_for_market<'a>(
 mod tests {
     use std::str::FromStr;
 
+    use serum_dex::state::FeeTier;
+
     use super::*;
 
     #[test]
@@ -199,6 +318,69 @@ mod tests {
             &client,
             &Pubkey::from_str("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin")?,
             &Pubkey::from_str("6oGsL2puUgySccKzn9XA9afqF217LfxP5ocq4B3LWsjy")?,
-        )
+        )?;
+        Ok(())
+    }
+
+    fn fill_event(
+        side: Side,
+        maker: bool,
+        native_qty_paid: u64,
+        native_qty_received: u64,
+        native_fee_or_rebate: u64,
+    ) -> EventView {
+        EventView::Fill {
+            side,
+            maker,
+            native_qty_paid,
+            native_qty_received,
+            native_fee_or_rebate,
+            order_id: 0,
+            owner: [0; 4],
+            owner_slot: 0,
+            fee_tier: FeeTier::Base,
+            client_order_id: None,
+        }
+    }
+
+    #[test]
+    fn parse_event_applies_fee_sign_per_side_and_role() {
+        // (side, maker, native_qty_paid, native_qty_received, fee, expected_quote, expected_base)
+        let cases = [
+            (Side::Bid, true, 1_000, 10, 5, 1_005, 10),
+            (Side::Bid, false, 1_000, 10, 5, 995, 10),
+            (Side::Ask, true, 10, 1_000, 5, 995, 10),
+            (Side::Ask, false, 10, 1_000, 5, 1_005, 10),
+        ];
+
+        for (side, maker, paid, received, fee, expected_quote, expected_base) in cases {
+            let event = fill_event(side, maker, paid, received, fee);
+            let fill = parse_event(event, 7, 1, 1)
+                .unwrap()
+                .expect("Fill events normalize to Some");
+            assert_eq!(
+                fill.native_quote_size, expected_quote,
+                "quote size for side={side:?} maker={maker}"
+            );
+            assert_eq!(
+                fill.native_base_size, expected_base,
+                "base size for side={side:?} maker={maker}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_event_skips_out_events() {
+        let event = EventView::Out {
+            side: Side::Bid,
+            release_funds: false,
+            native_qty_unlocked: 0,
+            native_qty_still_locked: 0,
+            order_id: 0,
+            owner: [0; 4],
+            owner_slot: 0,
+            client_order_id: None,
+        };
+        assert!(parse_event(event, 0, 1, 1).unwrap().is_none());
     }
 }
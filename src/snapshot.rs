@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use anyhow::{format_err, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::orderbook::{self, L2Snapshot};
+use crate::serum::{self, EventQueue, MarketPubkeys};
+
+/// `getMultipleAccounts` caps out at 100 pubkeys per request.
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Decoded state for one market: its resolved keys, live event queue, and
+/// L2 order book.
+pub struct MarketSnapshot {
+    pub keys: MarketPubkeys,
+    pub event_queue: EventQueue,
+    pub order_book: L2Snapshot,
+}
+
+/// Loads a snapshot of many markets' event queues and order books using
+/// chunked `getMultipleAccounts` calls instead of several sequential
+/// `getAccountData` round trips per market.
+pub fn load_markets_snapshot(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    markets: &[Pubkey],
+) -> Result<HashMap<Pubkey, MarketSnapshot>> {
+    let market_accounts = get_multiple_accounts_chunked(client, markets)?;
+
+    let mut keys_by_market = HashMap::with_capacity(markets.len());
+    for (market, account) in markets.iter().zip(market_accounts) {
+        let account = account.ok_or_else(|| format_err!("market {} does not exist", market))?;
+        let keys = serum::decode_market_keys(&account.data, program_id, market)?;
+        keys_by_market.insert(*market, keys);
+    }
+
+    let derived_keys: Vec<Pubkey> = keys_by_market
+        .values()
+        .flat_map(|keys| [*keys.event_q, *keys.bids, *keys.asks])
+        .collect();
+    let derived_accounts = get_multiple_accounts_chunked(client, &derived_keys)?;
+    let mut accounts_by_key: HashMap<Pubkey, Account> = derived_keys
+        .into_iter()
+        .zip(derived_accounts)
+        .filter_map(|(key, account)| account.map(|account| (key, account)))
+        .collect();
+
+    let mut snapshots = HashMap::with_capacity(markets.len());
+    for (market, keys) in keys_by_market {
+        let event_q_data = accounts_by_key
+            .remove(&*keys.event_q)
+            .ok_or_else(|| format_err!("missing event_q account for market {}", market))?;
+        let bids_data = accounts_by_key
+            .remove(&*keys.bids)
+            .ok_or_else(|| format_err!("missing bids account for market {}", market))?;
+        let asks_data = accounts_by_key
+            .remove(&*keys.asks)
+            .ok_or_else(|| format_err!("missing asks account for market {}", market))?;
+
+        let event_queue = serum::decode_event_queue(&event_q_data.data)?;
+        let order_book = orderbook::decode_l2_snapshot(&bids_data.data, &asks_data.data)?;
+
+        snapshots.insert(
+            market,
+            MarketSnapshot {
+                keys,
+                event_queue,
+                order_book,
+            },
+        );
+    }
+
+    Ok(snapshots)
+}
+
+fn get_multiple_accounts_chunked(client: &RpcClient, keys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+    let mut accounts = Vec::with_capacity(keys.len());
+    for chunk in keys.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+        accounts.extend(client.get_multiple_accounts(chunk)?);
+    }
+    Ok(accounts)
+}
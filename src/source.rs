@@ -0,0 +1,101 @@
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc;
+
+use crate::serum::{decode_event_queue, get_keys_for_market, EventQueue};
+
+/// One backend's feed of decoded event-queue updates for a single market,
+/// plus the lot sizes needed to price its fills.
+pub struct EventQueueFeed {
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub updates: mpsc::UnboundedReceiver<Result<EventQueue>>,
+}
+
+/// An ingestion backend capable of watching a market's event queue. The
+/// Kafka producer loop is written against this trait so it doesn't care
+/// whether updates come from RPC polling, a websocket subscription, or a
+/// geyser stream — only the chosen `EventQueueSource` changes.
+#[async_trait]
+pub trait EventQueueSource {
+    async fn subscribe(&self, program_id: &Pubkey, market: &Pubkey) -> Result<EventQueueFeed>;
+}
+
+/// Polls `get_account_data` on a fixed interval. The simplest source, and
+/// the worst-scaling one: every market watched costs its own RPC round
+/// trip every tick.
+pub struct RpcPoll {
+    pub rpc_url: String,
+    pub interval: Duration,
+}
+
+#[async_trait]
+impl EventQueueSource for RpcPoll {
+    async fn subscribe(&self, program_id: &Pubkey, market: &Pubkey) -> Result<EventQueueFeed> {
+        let client = RpcClient::new(self.rpc_url.clone());
+        let market_keys = get_keys_for_market(&client, program_id, market)?;
+        let event_q = *market_keys.event_q;
+        let interval = self.interval;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        thread::spawn(move || loop {
+            let decoded = client
+                .get_account_data(&event_q)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| decode_event_queue(&data));
+            if tx.send(decoded).is_err() {
+                break;
+            }
+            thread::sleep(interval);
+        });
+
+        Ok(EventQueueFeed {
+            coin_lot_size: market_keys.coin_lot_size,
+            pc_lot_size: market_keys.pc_lot_size,
+            updates: rx,
+        })
+    }
+}
+
+/// Subscribes to the market's `event_q` account over a Solana websocket
+/// connection (see [`crate::stream::stream_event_queue`]). `rpc_url` is
+/// taken separately since it can't be reliably derived from `ws_url`.
+pub struct WebSocket {
+    pub ws_url: String,
+    pub rpc_url: String,
+}
+
+#[async_trait]
+impl EventQueueSource for WebSocket {
+    async fn subscribe(&self, program_id: &Pubkey, market: &Pubkey) -> Result<EventQueueFeed> {
+        crate::stream::stream_event_queue(&self.ws_url, &self.rpc_url, program_id, market)
+    }
+}
+
+/// Subscribes to the market's `event_q` account over a Yellowstone gRPC
+/// geyser stream (see [`crate::geyser::stream_event_queue`]).
+pub struct Geyser {
+    pub rpc_url: String,
+    pub endpoint: String,
+    pub x_token: Option<String>,
+}
+
+#[async_trait]
+impl EventQueueSource for Geyser {
+    async fn subscribe(&self, program_id: &Pubkey, market: &Pubkey) -> Result<EventQueueFeed> {
+        let client = RpcClient::new(self.rpc_url.clone());
+        crate::geyser::stream_event_queue(
+            &client,
+            &self.endpoint,
+            self.x_token.as_deref(),
+            program_id,
+            market,
+        )
+        .await
+    }
+}
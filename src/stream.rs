@@ -0,0 +1,72 @@
+use std::thread;
+
+use anyhow::{format_err, Result};
+use solana_account_decoder::{UiAccountData, UiAccountEncoding};
+use solana_client::pubsub_client::PubsubClient;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc;
+
+use crate::serum::{decode_event_queue, get_keys_for_market, EventQueue};
+use crate::source::EventQueueFeed;
+
+/// Subscribes to a market's event queue account and re-decodes it on every
+/// change notification, pushing newly decoded queue snapshots as they arrive.
+///
+/// This replaces fixed-interval `get_account_data` polling with a push-based
+/// feed driven by `PubsubClient::account_subscribe`, the same primitive the
+/// solana-cli cluster_query account watcher uses for low-latency updates.
+///
+/// Takes `rpc_url` separately from `ws_url` rather than deriving one from
+/// the other — `ws://`/`wss://` don't map onto `http://`/`https://` by
+/// simple substring replacement (e.g. `"wss://...".replacen("ws", "https", 1)`
+/// yields the invalid scheme `"httpss://..."`), and plenty of clusters run
+/// their JSON-RPC and websocket endpoints on different hosts anyway.
+pub fn stream_event_queue(
+    ws_url: &str,
+    rpc_url: &str,
+    program_id: &Pubkey,
+    market: &Pubkey,
+) -> Result<EventQueueFeed> {
+    let client = RpcClient::new(rpc_url.to_string());
+    let market_keys = get_keys_for_market(&client, program_id, market)?;
+
+    let (account_sub, account_rx) = PubsubClient::account_subscribe(
+        ws_url,
+        &market_keys.event_q,
+        Some(RpcAccountInfoConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        }),
+    )
+    .map_err(|e| format_err!("event_q account_subscribe failed: {:?}", e))?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    thread::spawn(move || {
+        // Keep the subscription alive for as long as this thread runs.
+        let _account_sub = account_sub;
+        for update in account_rx {
+            let decoded = decode_account_update(&update.value.data);
+            if tx.send(decoded).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(EventQueueFeed {
+        coin_lot_size: market_keys.coin_lot_size,
+        pc_lot_size: market_keys.pc_lot_size,
+        updates: rx,
+    })
+}
+
+fn decode_account_update(data: &UiAccountData) -> Result<EventQueue> {
+    let bytes = data
+        .decode()
+        .ok_or_else(|| format_err!("event_q account update had no decodable data"))?;
+    decode_event_queue(&bytes)
+}